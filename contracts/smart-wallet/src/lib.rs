@@ -4,7 +4,7 @@ use soroban_sdk::{
     auth::{Context, CustomAccountInterface},
     contract, contracterror, contractimpl, contracttype,
     crypto::Hash,
-    Address, BytesN, Env, Vec,
+    panic_with_error, Address, BytesN, Env, String, Symbol, Vec,
 };
 
 mod base64_url;
@@ -14,8 +14,43 @@ mod webauthn;
 #[contracttype]
 pub enum DataKey {
     Admin,
-    Signer,
-    Recovery,
+    /// The public keys of every registered signer, used to enumerate and clear the set.
+    Signers,
+    /// The weight of a registered signer, keyed by its public key.
+    Signer(webauthn::PublicKey),
+    /// The last-seen WebAuthn signature counter for a signer, keyed by its public key.
+    SignCount(webauthn::PublicKey),
+    /// The combined signer weight required for `__check_auth` to succeed.
+    Threshold,
+    /// The addresses eligible to approve a signer recovery.
+    Guardians,
+    /// The number of distinct guardian approvals required to begin a signer recovery.
+    GuardianThreshold,
+    /// The guardians that have approved recovering to a given signer, keyed by that signer.
+    RotationApprovals(webauthn::PublicKey),
+    /// The expected RP ID hash (sha256 of the relying party's domain).
+    RpIdHash,
+    /// The client-data `origin` values this account will authenticate for.
+    AllowedOrigins,
+    /// The minimum elapsed ledger time required between successive authorizations of a given
+    /// (contract, function) pair, keyed by that pair.
+    RateLimit(Address, Symbol),
+    /// The ledger timestamp at which a (contract, function) pair was last authorized.
+    LastInvocation(Address, Symbol),
+    /// The elapsed ledger time a recovery must wait before it can be executed.
+    RecoveryDelay,
+    /// The recovery currently awaiting its timelock to elapse, if any.
+    PendingRecovery,
+}
+
+/// A recovery awaiting its timelock to elapse.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[contracttype]
+pub struct PendingRecovery {
+    /// The signer that will replace every currently registered signer once executed.
+    pub new_signer: webauthn::PublicKey,
+    /// The ledger timestamp at or after which `execute_recovery` may be called.
+    pub unlock_time: u64,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -27,16 +62,43 @@ pub enum AccountContractError {
     WebAuthnUserNotPresent = 3,
     WebAuthnUserNotVerified = 4,
     WebAuthnInvalidChallenge = 5,
+    WebAuthnStaleCounter = 6,
+    MissingAdmin = 7,
+    ThresholdNotMet = 8,
+    WebAuthnInvalidRpId = 9,
+    WebAuthnInvalidOrigin = 10,
+    PolicyRateLimited = 11,
+    InvalidThreshold = 12,
+    InvalidGuardianThreshold = 13,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 #[contracterror]
 pub enum RecoveryError {
-    RecoveryNotSet = 1000,
+    RecoveryNotPending = 1001,
+    RecoveryNotReady = 1002,
+    NotAGuardian = 1003,
 }
 
 pub trait Recovery {
-    fn rotate_signer(env: Env, new_signer: BytesN<65>) -> Result<(), RecoveryError>;
+    /// Records `guardian`'s approval of recovering to `new_signer`. Requires `guardian`'s
+    /// authorization, and `guardian` must be a configured guardian. Once distinct approvals for
+    /// `new_signer` reach the guardian threshold, begins a timelocked recovery and clears the
+    /// accumulated approvals.
+    fn approve_rotation(
+        env: Env,
+        guardian: Address,
+        new_signer: webauthn::PublicKey,
+    ) -> Result<(), RecoveryError>;
+
+    /// Applies the pending recovery once its timelock has elapsed, replacing every currently
+    /// registered signer with the recovered one.
+    fn execute_recovery(env: Env) -> Result<(), RecoveryError>;
+
+    /// Vetoes a pending recovery. Requires the account's own authorization (i.e. a registered
+    /// signer meeting the threshold), so compromised guardians alone cannot push a rotation
+    /// through.
+    fn cancel_recovery(env: Env) -> Result<(), RecoveryError>;
 }
 
 #[contract]
@@ -44,23 +106,382 @@ pub struct AccountContract;
 
 #[contractimpl]
 impl AccountContract {
-    pub fn __constructor(env: Env, public_key: BytesN<65>, recovery: Address) {
-        env.storage().instance().set(&DataKey::Signer, &public_key);
-        env.storage().instance().set(&DataKey::Recovery, &recovery);
+    pub fn __constructor(
+        env: Env,
+        admin: Address,
+        public_key: webauthn::PublicKey,
+        threshold: u32,
+        guardians: Vec<Address>,
+        guardian_threshold: u32,
+        recovery_delay: u64,
+        rp_id_hash: BytesN<32>,
+        allowed_origins: Vec<String>,
+    ) {
+        env.storage().instance().set(&DataKey::Admin, &admin);
+
+        let mut signers = Vec::new(&env);
+        signers.push_back(public_key.clone());
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        env.storage()
+            .instance()
+            .set(&DataKey::Signer(public_key), &1_u32);
+
+        if threshold == 0 || threshold > Self::total_signer_weight(&env) {
+            panic_with_error!(env, AccountContractError::InvalidThreshold);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+
+        if guardian_threshold == 0 || guardian_threshold > guardians.len() {
+            panic_with_error!(env, AccountContractError::InvalidGuardianThreshold);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Guardians, &guardians);
+        env.storage()
+            .instance()
+            .set(&DataKey::GuardianThreshold, &guardian_threshold);
+        env.storage()
+            .instance()
+            .set(&DataKey::RecoveryDelay, &recovery_delay);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::RpIdHash, &rp_id_hash);
+        env.storage()
+            .instance()
+            .set(&DataKey::AllowedOrigins, &allowed_origins);
+    }
+
+    /// Registers a signer with the given weight, or updates its weight if already registered.
+    /// Requires the admin's authorization.
+    pub fn add_signer(
+        env: Env,
+        public_key: webauthn::PublicKey,
+        weight: u32,
+    ) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        let mut signers = env
+            .storage()
+            .instance()
+            .get::<_, Vec<webauthn::PublicKey>>(&DataKey::Signers)
+            .unwrap_or(Vec::new(&env));
+
+        if !signers.contains(&public_key) {
+            signers.push_back(public_key.clone());
+            env.storage().instance().set(&DataKey::Signers, &signers);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Signer(public_key), &weight);
+
+        Ok(())
+    }
+
+    /// Unregisters a signer. Requires the admin's authorization.
+    pub fn remove_signer(
+        env: Env,
+        public_key: webauthn::PublicKey,
+    ) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        let mut signers = env
+            .storage()
+            .instance()
+            .get::<_, Vec<webauthn::PublicKey>>(&DataKey::Signers)
+            .unwrap_or(Vec::new(&env));
+
+        if let Some(index) = signers.iter().position(|signer| signer == public_key) {
+            signers.remove(index as u32);
+            env.storage().instance().set(&DataKey::Signers, &signers);
+        }
+
+        env.storage()
+            .instance()
+            .remove(&DataKey::Signer(public_key));
+
+        Ok(())
+    }
+
+    /// Sets the combined signer weight required to authorize a transaction.
+    /// Requires the admin's authorization.
+    pub fn set_threshold(env: Env, threshold: u32) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        if threshold == 0 || threshold > Self::total_signer_weight(&env) {
+            return Err(AccountContractError::InvalidThreshold);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Threshold, &threshold);
+        Ok(())
+    }
+
+    /// Sets the minimum elapsed ledger time required between successive authorizations of
+    /// `fn_name` on `contract`. Requires the admin's authorization.
+    pub fn set_rate_limit(
+        env: Env,
+        contract: Address,
+        fn_name: Symbol,
+        min_interval: u64,
+    ) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+        env.storage()
+            .instance()
+            .set(&DataKey::RateLimit(contract, fn_name), &min_interval);
+        Ok(())
+    }
+
+    /// Registers a guardian eligible to approve signer recovery. Requires the admin's
+    /// authorization.
+    pub fn add_guardian(env: Env, guardian: Address) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        let mut guardians = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&DataKey::Guardians)
+            .unwrap_or(Vec::new(&env));
+
+        if !guardians.contains(&guardian) {
+            guardians.push_back(guardian);
+            env.storage()
+                .instance()
+                .set(&DataKey::Guardians, &guardians);
+        }
+
+        Ok(())
+    }
+
+    /// Unregisters a guardian. Requires the admin's authorization.
+    pub fn remove_guardian(env: Env, guardian: Address) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        let mut guardians = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&DataKey::Guardians)
+            .unwrap_or(Vec::new(&env));
+
+        if let Some(index) = guardians.iter().position(|g| g == guardian) {
+            guardians.remove(index as u32);
+            env.storage()
+                .instance()
+                .set(&DataKey::Guardians, &guardians);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the number of distinct guardian approvals required to begin a signer recovery.
+    /// Requires the admin's authorization.
+    pub fn set_guardian_threshold(
+        env: Env,
+        guardian_threshold: u32,
+    ) -> Result<(), AccountContractError> {
+        Self::require_admin(&env)?;
+
+        let guardians = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&DataKey::Guardians)
+            .unwrap_or(Vec::new(&env));
+
+        if guardian_threshold == 0 || guardian_threshold > guardians.len() {
+            return Err(AccountContractError::InvalidGuardianThreshold);
+        }
+
+        env.storage()
+            .instance()
+            .set(&DataKey::GuardianThreshold, &guardian_threshold);
+        Ok(())
+    }
+
+    fn require_admin(env: &Env) -> Result<(), AccountContractError> {
+        let admin = env
+            .storage()
+            .instance()
+            .get::<_, Address>(&DataKey::Admin)
+            .ok_or(AccountContractError::MissingAdmin)?;
+        admin.require_auth();
+        Ok(())
+    }
+
+    /// Sums the weights of every currently registered signer.
+    fn total_signer_weight(env: &Env) -> u32 {
+        let signers = env
+            .storage()
+            .instance()
+            .get::<_, Vec<webauthn::PublicKey>>(&DataKey::Signers)
+            .unwrap_or(Vec::new(env));
+
+        let mut total_weight = 0_u32;
+        for signer in signers.iter() {
+            total_weight += env
+                .storage()
+                .instance()
+                .get::<_, u32>(&DataKey::Signer(signer))
+                .unwrap_or(0);
+        }
+
+        total_weight
+    }
+
+    /// Enforces the configured per-(contract, function) rate limits against `auth_contexts`,
+    /// recording the current ledger timestamp against each limited target it allows.
+    fn enforce_rate_limits(
+        env: &Env,
+        auth_contexts: &Vec<Context>,
+    ) -> Result<(), AccountContractError> {
+        for context in auth_contexts.iter() {
+            let Context::Contract(contract_context) = context else {
+                continue;
+            };
+
+            let rate_limit_key = DataKey::RateLimit(
+                contract_context.contract.clone(),
+                contract_context.fn_name.clone(),
+            );
+            let Some(min_interval) = env.storage().instance().get::<_, u64>(&rate_limit_key) else {
+                continue;
+            };
+
+            let last_invocation_key = DataKey::LastInvocation(
+                contract_context.contract.clone(),
+                contract_context.fn_name.clone(),
+            );
+            let now = env.ledger().timestamp();
+            let last_invocation = env.storage().instance().get::<_, u64>(&last_invocation_key);
+
+            if let Some(last_invocation) = last_invocation {
+                if now.saturating_sub(last_invocation) < min_interval {
+                    return Err(AccountContractError::PolicyRateLimited);
+                }
+            }
+
+            env.storage().instance().set(&last_invocation_key, &now);
+        }
+
+        Ok(())
     }
 }
 
 #[contractimpl]
 impl Recovery for AccountContract {
-    fn rotate_signer(env: Env, new_signer: BytesN<65>) -> Result<(), RecoveryError> {
-        let recovery = env
+    fn approve_rotation(
+        env: Env,
+        guardian: Address,
+        new_signer: webauthn::PublicKey,
+    ) -> Result<(), RecoveryError> {
+        guardian.require_auth();
+
+        let guardians = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&DataKey::Guardians)
+            .unwrap_or(Vec::new(&env));
+        if !guardians.contains(&guardian) {
+            return Err(RecoveryError::NotAGuardian);
+        }
+
+        let approvals_key = DataKey::RotationApprovals(new_signer.clone());
+        let stored_approvals = env
+            .storage()
+            .instance()
+            .get::<_, Vec<Address>>(&approvals_key)
+            .unwrap_or(Vec::new(&env));
+
+        // Drop approvals from addresses that are no longer guardians, so a guardian removed
+        // after approving can't contribute a stale vote toward the threshold.
+        let mut approvals = Vec::new(&env);
+        for approver in stored_approvals.iter() {
+            if guardians.contains(&approver) {
+                approvals.push_back(approver);
+            }
+        }
+
+        if !approvals.contains(&guardian) {
+            approvals.push_back(guardian);
+        }
+
+        let guardian_threshold = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&DataKey::GuardianThreshold)
+            .unwrap_or(0);
+
+        if approvals.len() < guardian_threshold {
+            env.storage().instance().set(&approvals_key, &approvals);
+            return Ok(());
+        }
+
+        env.storage().instance().remove(&approvals_key);
+
+        let delay = env
+            .storage()
+            .instance()
+            .get::<_, u64>(&DataKey::RecoveryDelay)
+            .unwrap_or(0);
+        let unlock_time = env.ledger().timestamp() + delay;
+
+        env.storage().instance().set(
+            &DataKey::PendingRecovery,
+            &PendingRecovery {
+                new_signer,
+                unlock_time,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn execute_recovery(env: Env) -> Result<(), RecoveryError> {
+        let pending = env
+            .storage()
+            .instance()
+            .get::<_, PendingRecovery>(&DataKey::PendingRecovery)
+            .ok_or(RecoveryError::RecoveryNotPending)?;
+
+        if env.ledger().timestamp() < pending.unlock_time {
+            return Err(RecoveryError::RecoveryNotReady);
+        }
+
+        // Replace the entire signer set with the recovered signer, dropping every previously
+        // registered signer's weight so it can't be revived later.
+        let old_signers = env
             .storage()
             .instance()
-            .get::<_, Address>(&DataKey::Recovery)
-            .ok_or(RecoveryError::RecoveryNotSet)?;
-        recovery.require_auth();
+            .get::<_, Vec<webauthn::PublicKey>>(&DataKey::Signers)
+            .unwrap_or(Vec::new(&env));
+        for signer in old_signers.iter() {
+            env.storage().instance().remove(&DataKey::Signer(signer));
+        }
+
+        let mut signers = Vec::new(&env);
+        signers.push_back(pending.new_signer.clone());
+        env.storage().instance().set(&DataKey::Signers, &signers);
+        env.storage()
+            .instance()
+            .set(&DataKey::Signer(pending.new_signer), &1_u32);
+        env.storage().instance().set(&DataKey::Threshold, &1_u32);
 
-        env.storage().instance().set(&DataKey::Signer, &new_signer);
+        env.storage().instance().remove(&DataKey::PendingRecovery);
+
+        Ok(())
+    }
+
+    fn cancel_recovery(env: Env) -> Result<(), RecoveryError> {
+        env.current_contract_address().require_auth();
+
+        if !env.storage().instance().has(&DataKey::PendingRecovery) {
+            return Err(RecoveryError::RecoveryNotPending);
+        }
+
+        env.storage().instance().remove(&DataKey::PendingRecovery);
 
         Ok(())
     }
@@ -69,21 +490,45 @@ impl Recovery for AccountContract {
 #[contractimpl]
 impl CustomAccountInterface for AccountContract {
     type Error = AccountContractError;
-    type Signature = webauthn::WebAuthnCredential;
+    type Signature = Vec<webauthn::WebAuthnCredential>;
 
     fn __check_auth(
         env: Env,
         signature_payload: Hash<32>,
         signatures: Self::Signature,
-        _auth_contexts: Vec<Context>,
+        auth_contexts: Vec<Context>,
     ) -> Result<(), Self::Error> {
-        let public_key = env
+        let threshold = env
             .storage()
             .instance()
-            .get::<_, BytesN<65>>(&DataKey::Signer)
+            .get::<_, u32>(&DataKey::Threshold)
             .ok_or(AccountContractError::MissingSigner)?;
 
-        webauthn::verify(&env, &signature_payload, &signatures, &public_key);
+        let mut verified_signers: Vec<webauthn::PublicKey> = Vec::new(&env);
+        let mut total_weight: u32 = 0;
+
+        for credential in signatures.iter() {
+            if verified_signers.contains(&credential.public_key) {
+                continue;
+            }
+
+            let weight = env
+                .storage()
+                .instance()
+                .get::<_, u32>(&DataKey::Signer(credential.public_key.clone()))
+                .ok_or(AccountContractError::MissingSigner)?;
+
+            webauthn::verify(&env, &signature_payload, &credential);
+
+            verified_signers.push_back(credential.public_key.clone());
+            total_weight += weight;
+        }
+
+        if total_weight < threshold {
+            return Err(AccountContractError::ThresholdNotMet);
+        }
+
+        Self::enforce_rate_limits(&env, &auth_contexts)?;
 
         Ok(())
     }
@@ -3,20 +3,36 @@
 extern crate std;
 
 use crate::webauthn::{
-    WebAuthnCredential, AUTH_DATA_FLAG_OFFSET, AUTH_DATA_FLAG_UP, AUTH_DATA_FLAG_UV,
-    ENCODED_CHALLENGE_LEN,
+    PublicKey, WebAuthnCredential, AUTH_DATA_FLAG_OFFSET, AUTH_DATA_FLAG_UP, AUTH_DATA_FLAG_UV,
+    AUTH_DATA_SIGN_COUNT_OFFSET, ENCODED_CHALLENGE_LEN,
 };
 
 use soroban_sdk::{
-    testutils::{Address as _, BytesN as _},
-    vec, BytesN, IntoVal,
+    auth::{Context, ContractContext},
+    testutils::{Address as _, BytesN as _, Ledger as _},
+    vec, BytesN, IntoVal, Symbol,
 };
 use std::string::ToString;
 
 use super::*;
+use ed25519_dalek::{Signer as _, SigningKey as Ed25519SigningKey};
 use p256::ecdsa::{signature::SignerMut, SigningKey, VerifyingKey};
 use rand_core::OsRng;
-use soroban_sdk::{Address, Bytes};
+use soroban_sdk::{Address, Bytes, String};
+
+/// The RP ID hash this test suite's contracts are configured to accept.
+fn test_rp_id_hash(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[7u8; 32])
+}
+
+/// The client-data origin this test suite's contracts are configured to accept.
+const TEST_ORIGIN: &str = "https://example.com";
+
+fn test_allowed_origins(env: &Env) -> Vec<String> {
+    let mut allowed_origins = Vec::new(env);
+    allowed_origins.push_back(String::from_str(env, TEST_ORIGIN));
+    allowed_origins
+}
 
 fn generate_test_p256_keypair(env: Env) -> (BytesN<65>, SigningKey) {
     let signing_key = SigningKey::random(&mut OsRng);
@@ -30,25 +46,89 @@ fn generate_test_p256_keypair(env: Env) -> (BytesN<65>, SigningKey) {
     (public_key, signing_key)
 }
 
-fn sign(env: Env, challenge_hash: &[u8; 32], signing_key: &mut SigningKey) -> WebAuthnCredential {
+fn generate_test_ed25519_keypair(env: Env) -> (BytesN<32>, Ed25519SigningKey) {
+    let signing_key = Ed25519SigningKey::generate(&mut OsRng);
+    let public_key = BytesN::from_array(&env, signing_key.verifying_key().as_bytes());
+
+    (public_key, signing_key)
+}
+
+/// Registers an `AccountContract` with a single signer at weight 1 and threshold 1,
+/// preserving the single-signer behavior the other tests exercise.
+fn register_contract(env: &Env, public_key: BytesN<65>) -> Address {
+    register_contract_with_signer(env, PublicKey::Secp256r1(public_key))
+}
+
+/// The recovery timelock this test suite's contracts are configured with, unless noted otherwise.
+const TEST_RECOVERY_DELAY: u64 = 3600;
+
+fn register_contract_with_signer(env: &Env, public_key: PublicKey) -> Address {
+    let mut guardians = Vec::new(env);
+    guardians.push_back(Address::generate(env));
+    register_contract_with_guardians(env, public_key, guardians, 1, TEST_RECOVERY_DELAY)
+}
+
+fn register_contract_with_guardians(
+    env: &Env,
+    public_key: PublicKey,
+    guardians: Vec<Address>,
+    guardian_threshold: u32,
+    recovery_delay: u64,
+) -> Address {
+    let admin = Address::generate(env);
+    let args = (
+        admin,
+        public_key,
+        1_u32,
+        guardians,
+        guardian_threshold,
+        recovery_delay,
+        test_rp_id_hash(env),
+        test_allowed_origins(env),
+    );
+    env.register(AccountContract {}, args)
+}
+
+fn sign(
+    env: Env,
+    public_key: BytesN<65>,
+    challenge_hash: &[u8; 32],
+    signing_key: &mut SigningKey,
+) -> WebAuthnCredential {
+    sign_with_count(env, public_key, challenge_hash, signing_key, 0)
+}
+
+fn sign_with_count(
+    env: Env,
+    public_key: BytesN<65>,
+    challenge_hash: &[u8; 32],
+    signing_key: &mut SigningKey,
+    sign_count: u32,
+) -> WebAuthnCredential {
     let mut authenticator_data = Bytes::from_slice(&env, &[0; 37]);
 
-    // Fill in RP ID Hash. It's not verified by the contract.
-    for i in 0..32 {
-        authenticator_data.set(i, i as u8);
+    // Fill in the RP ID hash the contract is configured to expect.
+    for (i, byte) in test_rp_id_hash(&env).to_array().iter().enumerate() {
+        authenticator_data.set(i as u32, *byte);
     }
 
     // Set flags: User Present (UP) and User Verified (UV)s
     authenticator_data.set(AUTH_DATA_FLAG_OFFSET, AUTH_DATA_FLAG_UP | AUTH_DATA_FLAG_UV);
 
+    // Set the signature counter
+    for (i, byte) in sign_count.to_be_bytes().iter().enumerate() {
+        authenticator_data.set(AUTH_DATA_SIGN_COUNT_OFFSET + i as u32, *byte);
+    }
+
     // Create the challenge string
     let mut expected_challenge_buffer = [0_u8; ENCODED_CHALLENGE_LEN as usize];
     base64_url::encode(&mut expected_challenge_buffer, &challenge_hash[0..32]);
     let challenge_str = std::str::from_utf8(&expected_challenge_buffer).unwrap();
 
     let client_data_json = std::format!(
-        r#"{{"type":"webauthn.get","challenge":"{}","origin":"https://example.com"}}"#,
-        challenge_str
+        r#"{{"type":"webauthn.get","challenge":"{}","origin":"{}"}}"#,
+        challenge_str,
+        TEST_ORIGIN
     );
 
     // Create the client data hash
@@ -80,33 +160,88 @@ fn sign(env: Env, challenge_hash: &[u8; 32], signing_key: &mut SigningKey) -> We
     raw_signature_bytes[32..64].copy_from_slice(s_bytes.as_slice());
 
     WebAuthnCredential {
+        public_key: PublicKey::Secp256r1(public_key),
         client_data_json: Bytes::from_slice(&env, client_data_json_bytes),
         authenticator_data,
         signature: BytesN::from_array(&env, &raw_signature_bytes),
     }
 }
 
+fn sign_ed25519(
+    env: Env,
+    public_key: BytesN<32>,
+    challenge_hash: &[u8; 32],
+    signing_key: &Ed25519SigningKey,
+) -> WebAuthnCredential {
+    let mut authenticator_data = Bytes::from_slice(&env, &[0; 37]);
+
+    // Fill in the RP ID hash the contract is configured to expect.
+    for (i, byte) in test_rp_id_hash(&env).to_array().iter().enumerate() {
+        authenticator_data.set(i as u32, *byte);
+    }
+
+    // Set flags: User Present (UP) and User Verified (UV)
+    authenticator_data.set(AUTH_DATA_FLAG_OFFSET, AUTH_DATA_FLAG_UP | AUTH_DATA_FLAG_UV);
+
+    // Create the challenge string
+    let mut expected_challenge_buffer = [0_u8; ENCODED_CHALLENGE_LEN as usize];
+    base64_url::encode(&mut expected_challenge_buffer, &challenge_hash[0..32]);
+    let challenge_str = std::str::from_utf8(&expected_challenge_buffer).unwrap();
+
+    let client_data_json = std::format!(
+        r#"{{"type":"webauthn.get","challenge":"{}","origin":"{}"}}"#,
+        challenge_str,
+        TEST_ORIGIN
+    );
+
+    // Create the client data hash
+    let client_data_json_bytes = client_data_json.as_bytes();
+    let client_data_hash = env
+        .crypto()
+        .sha256(&Bytes::from_slice(&env, client_data_json_bytes));
+
+    let mut message = authenticator_data.clone();
+    message.extend_from_slice(&client_data_hash.to_array());
+
+    let mut message_std_vec = std::vec::Vec::with_capacity(message.len() as usize);
+    for i in 0..message.len() {
+        message_std_vec.push(message.get(i).unwrap());
+    }
+
+    // Sign the message (authenticator data + client data hash)
+    let signature = signing_key.sign(&message_std_vec);
+
+    WebAuthnCredential {
+        public_key: PublicKey::Ed25519(public_key),
+        client_data_json: Bytes::from_slice(&env, client_data_json_bytes),
+        authenticator_data,
+        signature: BytesN::from_array(&env, &signature.to_bytes()),
+    }
+}
+
 #[test]
 fn test_validate_signature() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env
         .crypto()
         .sha256(&Bytes::from_array(&env, &payload.to_array()));
 
-    let credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     )
     .unwrap();
@@ -117,15 +252,17 @@ fn test_webauthn_invalid_type() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     let original_challenge_str = {
         let mut temp_challenge_buf = [0u8; ENCODED_CHALLENGE_LEN as usize];
@@ -144,7 +281,7 @@ fn test_webauthn_invalid_type() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
 
@@ -156,15 +293,17 @@ fn test_webauthn_client_data_duplicate_fields() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     let original_challenge_str = {
         let mut temp_challenge_buf = [0u8; ENCODED_CHALLENGE_LEN as usize];
@@ -184,7 +323,7 @@ fn test_webauthn_client_data_duplicate_fields() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
 
@@ -199,15 +338,17 @@ fn test_webauthn_user_not_present() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     // Clear the User Present flag (UP - bit 0)
     let mut auth_data_vec = std::vec::Vec::new();
@@ -220,7 +361,7 @@ fn test_webauthn_user_not_present() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
     assert_eq!(
@@ -234,15 +375,17 @@ fn test_webauthn_user_not_verified() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     // Clear the User Verified flag (UV - bit 2)
     let mut auth_data_vec = std::vec::Vec::new();
@@ -255,7 +398,7 @@ fn test_webauthn_user_not_verified() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
     assert_eq!(
@@ -269,15 +412,17 @@ fn test_webauthn_invalid_challenge_content() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload_sign: BytesN<32> = BytesN::random(&env);
     let payload_hash_sign = env.crypto().sha256(&payload_sign.clone().into());
 
-    let credential = sign(env.clone(), &payload_hash_sign.to_array(), &mut signing_key);
+    let credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash_sign.to_array(),
+        &mut signing_key,
+    );
 
     let different_payload: BytesN<32> = BytesN::random(&env);
     let different_payload_hash = env.crypto().sha256(&different_payload.clone().into());
@@ -285,7 +430,7 @@ fn test_webauthn_invalid_challenge_content() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &different_payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
     assert_eq!(
@@ -299,15 +444,17 @@ fn test_webauthn_invalid_challenge_length_in_client_data() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     let original_challenge_str = {
         let mut temp_challenge_buf = [0u8; ENCODED_CHALLENGE_LEN as usize];
@@ -329,7 +476,7 @@ fn test_webauthn_invalid_challenge_length_in_client_data() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
 
@@ -344,15 +491,17 @@ fn test_webauthn_tampered_signature() {
     let env = Env::default();
 
     let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
-
-    let admin = Address::generate(&env);
-    let args = (admin, public_key.clone());
-    let contract_address = env.register(AccountContract {}, args);
+    let contract_address = register_contract(&env, public_key.clone());
 
     let payload: BytesN<32> = BytesN::random(&env);
     let payload_hash = env.crypto().sha256(&payload.clone().into());
 
-    let mut credential = sign(env.clone(), &payload_hash.to_array(), &mut signing_key);
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
 
     // Tamper with the signature
     let mut sig_bytes = credential.signature.to_array();
@@ -362,9 +511,621 @@ fn test_webauthn_tampered_signature() {
     let result = env.try_invoke_contract_check_auth::<AccountContractError>(
         &contract_address,
         &BytesN::from_array(&env, &payload_hash.to_array()),
-        credential.into_val(&env),
+        vec![&env, credential].into_val(&env),
         &vec![&env],
     );
 
     assert!(result.is_err());
 }
+
+#[test]
+fn test_webauthn_sign_count_advances() {
+    let env = Env::default();
+
+    let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key.clone());
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    let credential = sign_with_count(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+        1,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_webauthn_stale_counter_rejected() {
+    let env = Env::default();
+
+    let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key.clone());
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    let credential = sign_with_count(
+        env.clone(),
+        public_key.clone(),
+        &payload_hash.to_array(),
+        &mut signing_key,
+        5,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+
+    // A clone of the authenticator replaying the same (or a lower) counter must be rejected.
+    let replayed_payload: BytesN<32> = BytesN::random(&env);
+    let replayed_payload_hash = env.crypto().sha256(&replayed_payload.clone().into());
+    let replayed_credential = sign_with_count(
+        env.clone(),
+        public_key,
+        &replayed_payload_hash.to_array(),
+        &mut signing_key,
+        5,
+    );
+
+    let result = env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &replayed_payload_hash.to_array()),
+        vec![&env, replayed_credential].into_val(&env),
+        &vec![&env],
+    );
+
+    assert_eq!(result, Err(Ok(AccountContractError::WebAuthnStaleCounter)));
+}
+
+#[test]
+fn test_webauthn_invalid_rp_id() {
+    let env = Env::default();
+
+    let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key.clone());
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
+
+    // Tamper with the RP ID hash so it no longer matches the account's configured relying party.
+    let mut auth_data_vec = std::vec::Vec::new();
+    for i in 0..credential.authenticator_data.len() {
+        auth_data_vec.push(credential.authenticator_data.get(i).unwrap());
+    }
+    auth_data_vec[0] ^= 0xFF;
+    credential.authenticator_data = Bytes::from_slice(&env, &auth_data_vec);
+
+    let result = env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    );
+
+    assert_eq!(result, Err(Ok(AccountContractError::WebAuthnInvalidRpId)));
+}
+
+#[test]
+fn test_webauthn_invalid_origin() {
+    let env = Env::default();
+
+    let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key.clone());
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    let mut credential = sign(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
+
+    let original_challenge_str = {
+        let mut temp_challenge_buf = [0u8; ENCODED_CHALLENGE_LEN as usize];
+        base64_url::encode(&mut temp_challenge_buf, &payload_hash.to_array());
+        std::str::from_utf8(&temp_challenge_buf)
+            .unwrap()
+            .to_string()
+    };
+
+    let untrusted_origin_json_str = std::format!(
+        r#"{{"type":"webauthn.get","challenge":"{}","origin":"https://evil.example"}}"#,
+        original_challenge_str
+    );
+    credential.client_data_json = Bytes::from_slice(&env, untrusted_origin_json_str.as_bytes());
+
+    let result = env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    );
+
+    assert_eq!(result, Err(Ok(AccountContractError::WebAuthnInvalidOrigin)));
+}
+
+#[test]
+fn test_weighted_multisig_threshold() {
+    let env = Env::default();
+
+    let (public_key_a, mut signing_key_a) = generate_test_p256_keypair(env.clone());
+    let (public_key_b, mut signing_key_b) = generate_test_p256_keypair(env.clone());
+
+    let contract_address =
+        register_contract_with_signer(&env, PublicKey::Secp256r1(public_key_a.clone()));
+
+    env.mock_all_auths();
+    env.as_contract(&contract_address, || {
+        AccountContract::add_signer(env.clone(), PublicKey::Secp256r1(public_key_b.clone()), 1)
+            .unwrap();
+        AccountContract::set_threshold(env.clone(), 2).unwrap();
+    });
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    // Signer A alone has weight 1, below the threshold of 2.
+    let credential_a = sign(
+        env.clone(),
+        public_key_a.clone(),
+        &payload_hash.to_array(),
+        &mut signing_key_a,
+    );
+
+    let result = env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential_a.clone()].into_val(&env),
+        &vec![&env],
+    );
+    assert_eq!(result, Err(Ok(AccountContractError::ThresholdNotMet)));
+
+    // Signers A and B together reach the threshold of 2.
+    let credential_b = sign(
+        env.clone(),
+        public_key_b,
+        &payload_hash.to_array(),
+        &mut signing_key_b,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential_a, credential_b].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_threshold_cannot_be_set_to_zero_or_above_signer_weight() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key);
+
+    env.mock_all_auths();
+    env.as_contract(&contract_address, || {
+        // A threshold of 0 would let `__check_auth` succeed with no signatures at all.
+        assert_eq!(
+            AccountContract::set_threshold(env.clone(), 0),
+            Err(AccountContractError::InvalidThreshold)
+        );
+
+        // The only registered signer has weight 1, so a threshold of 2 could never be met.
+        assert_eq!(
+            AccountContract::set_threshold(env.clone(), 2),
+            Err(AccountContractError::InvalidThreshold)
+        );
+    });
+}
+
+#[test]
+fn test_policy_rate_limit() {
+    let env = Env::default();
+
+    let (public_key, mut signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key.clone());
+
+    let target_contract = Address::generate(&env);
+    let fn_name = Symbol::new(&env, "transfer");
+
+    env.mock_all_auths();
+    env.as_contract(&contract_address, || {
+        AccountContract::set_rate_limit(env.clone(), target_contract.clone(), fn_name.clone(), 60)
+            .unwrap();
+    });
+
+    let auth_contexts = vec![
+        &env,
+        Context::Contract(ContractContext {
+            contract: target_contract,
+            fn_name,
+            args: Vec::new(&env),
+        }),
+    ];
+
+    env.ledger().set_timestamp(1000);
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+    let credential = sign(
+        env.clone(),
+        public_key.clone(),
+        &payload_hash.to_array(),
+        &mut signing_key,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &auth_contexts,
+    )
+    .unwrap();
+
+    // A second authorization of the same target before the interval elapses is rejected.
+    let replayed_payload: BytesN<32> = BytesN::random(&env);
+    let replayed_payload_hash = env.crypto().sha256(&replayed_payload.clone().into());
+    let replayed_credential = sign(
+        env.clone(),
+        public_key.clone(),
+        &replayed_payload_hash.to_array(),
+        &mut signing_key,
+    );
+
+    let result = env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &replayed_payload_hash.to_array()),
+        vec![&env, replayed_credential].into_val(&env),
+        &auth_contexts,
+    );
+    assert_eq!(result, Err(Ok(AccountContractError::PolicyRateLimited)));
+
+    // Once the interval elapses, the same target can be authorized again.
+    env.ledger().set_timestamp(1061);
+
+    let later_payload: BytesN<32> = BytesN::random(&env);
+    let later_payload_hash = env.crypto().sha256(&later_payload.clone().into());
+    let later_credential = sign(
+        env.clone(),
+        public_key,
+        &later_payload_hash.to_array(),
+        &mut signing_key,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &later_payload_hash.to_array()),
+        vec![&env, later_credential].into_val(&env),
+        &auth_contexts,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_recovery_timelock_enforced() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+    let guardian = Address::generate(&env);
+    let mut guardians = Vec::new(&env);
+    guardians.push_back(guardian.clone());
+    let contract_address = register_contract_with_guardians(
+        &env,
+        PublicKey::Secp256r1(public_key),
+        guardians,
+        1,
+        TEST_RECOVERY_DELAY,
+    );
+
+    let (new_public_key, mut new_signing_key) = generate_test_p256_keypair(env.clone());
+
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1000);
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            guardian,
+            PublicKey::Secp256r1(new_public_key.clone()),
+        )
+        .unwrap();
+    });
+
+    // Executing before the timelock elapses is rejected.
+    let result = env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone())
+    });
+    assert_eq!(result, Err(RecoveryError::RecoveryNotReady));
+
+    // Once the timelock elapses, the recovery can be executed.
+    env.ledger().set_timestamp(1000 + TEST_RECOVERY_DELAY);
+    env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone()).unwrap();
+    });
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+    let credential = sign(
+        env.clone(),
+        new_public_key,
+        &payload_hash.to_array(),
+        &mut new_signing_key,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_recovery_can_be_canceled() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+    let guardian = Address::generate(&env);
+    let mut guardians = Vec::new(&env);
+    guardians.push_back(guardian.clone());
+    let contract_address = register_contract_with_guardians(
+        &env,
+        PublicKey::Secp256r1(public_key),
+        guardians,
+        1,
+        TEST_RECOVERY_DELAY,
+    );
+
+    let (new_public_key, _new_signing_key) = generate_test_p256_keypair(env.clone());
+
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1000);
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(env.clone(), guardian, PublicKey::Secp256r1(new_public_key))
+            .unwrap();
+    });
+
+    env.as_contract(&contract_address, || {
+        Recovery::cancel_recovery(env.clone()).unwrap();
+    });
+
+    env.ledger().set_timestamp(1000 + TEST_RECOVERY_DELAY);
+    let result = env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone())
+    });
+    assert_eq!(result, Err(RecoveryError::RecoveryNotPending));
+}
+
+#[test]
+fn test_guardian_threshold_requires_multiple_approvals() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+
+    let guardian_a = Address::generate(&env);
+    let guardian_b = Address::generate(&env);
+    let guardian_c = Address::generate(&env);
+    let mut guardians = Vec::new(&env);
+    guardians.push_back(guardian_a.clone());
+    guardians.push_back(guardian_b.clone());
+    guardians.push_back(guardian_c);
+
+    let contract_address = register_contract_with_guardians(
+        &env,
+        PublicKey::Secp256r1(public_key),
+        guardians,
+        2,
+        TEST_RECOVERY_DELAY,
+    );
+
+    let (new_public_key, mut new_signing_key) = generate_test_p256_keypair(env.clone());
+
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1000);
+
+    // A single approval, below the threshold of 2, doesn't start the timelock.
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            guardian_a,
+            PublicKey::Secp256r1(new_public_key.clone()),
+        )
+        .unwrap();
+    });
+
+    let result = env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone())
+    });
+    assert_eq!(result, Err(RecoveryError::RecoveryNotPending));
+
+    // A second, distinct guardian's approval reaches the threshold and starts the timelock.
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            guardian_b,
+            PublicKey::Secp256r1(new_public_key.clone()),
+        )
+        .unwrap();
+    });
+
+    let result = env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone())
+    });
+    assert_eq!(result, Err(RecoveryError::RecoveryNotReady));
+
+    env.ledger().set_timestamp(1000 + TEST_RECOVERY_DELAY);
+    env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone()).unwrap();
+    });
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+    let credential = sign(
+        env.clone(),
+        new_public_key,
+        &payload_hash.to_array(),
+        &mut new_signing_key,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_removed_guardian_approval_does_not_count_toward_threshold() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+
+    let guardian_a = Address::generate(&env);
+    let guardian_b = Address::generate(&env);
+    let guardian_c = Address::generate(&env);
+    let mut guardians = Vec::new(&env);
+    guardians.push_back(guardian_a.clone());
+    guardians.push_back(guardian_b.clone());
+    guardians.push_back(guardian_c);
+
+    let contract_address = register_contract_with_guardians(
+        &env,
+        PublicKey::Secp256r1(public_key),
+        guardians,
+        2,
+        TEST_RECOVERY_DELAY,
+    );
+
+    let (new_public_key, _new_signing_key) = generate_test_p256_keypair(env.clone());
+
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1000);
+
+    // Guardian A approves, then is removed before reaching the threshold.
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            guardian_a.clone(),
+            PublicKey::Secp256r1(new_public_key.clone()),
+        )
+        .unwrap();
+        AccountContract::remove_guardian(env.clone(), guardian_a).unwrap();
+    });
+
+    // Guardian B's approval alone must not push the stale vote from the now-removed
+    // guardian A over the threshold of 2.
+    env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            guardian_b,
+            PublicKey::Secp256r1(new_public_key),
+        )
+        .unwrap();
+    });
+
+    let result = env.as_contract(&contract_address, || {
+        Recovery::execute_recovery(env.clone())
+    });
+    assert_eq!(result, Err(RecoveryError::RecoveryNotPending));
+}
+
+#[test]
+fn test_non_guardian_cannot_approve_rotation() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key);
+
+    let not_a_guardian = Address::generate(&env);
+    let (new_public_key, _new_signing_key) = generate_test_p256_keypair(env.clone());
+
+    env.mock_all_auths();
+    let result = env.as_contract(&contract_address, || {
+        Recovery::approve_rotation(
+            env.clone(),
+            not_a_guardian,
+            PublicKey::Secp256r1(new_public_key),
+        )
+    });
+    assert_eq!(result, Err(RecoveryError::NotAGuardian));
+}
+
+#[test]
+fn test_guardian_threshold_cannot_be_set_to_zero_or_above_guardian_count() {
+    let env = Env::default();
+
+    let (public_key, _signing_key) = generate_test_p256_keypair(env.clone());
+    let contract_address = register_contract(&env, public_key);
+
+    env.mock_all_auths();
+    env.as_contract(&contract_address, || {
+        // A guardian threshold of 0 would let any single guardian's approval start a
+        // recovery on its own, defeating the M-of-N guarantee.
+        assert_eq!(
+            AccountContract::set_guardian_threshold(env.clone(), 0),
+            Err(AccountContractError::InvalidGuardianThreshold)
+        );
+
+        // `register_contract` configures a single guardian, so a threshold of 2 could never
+        // be met.
+        assert_eq!(
+            AccountContract::set_guardian_threshold(env.clone(), 2),
+            Err(AccountContractError::InvalidGuardianThreshold)
+        );
+    });
+}
+
+#[test]
+fn test_webauthn_ed25519_signer() {
+    let env = Env::default();
+
+    let (public_key, signing_key) = generate_test_ed25519_keypair(env.clone());
+    let contract_address =
+        register_contract_with_signer(&env, PublicKey::Ed25519(public_key.clone()));
+
+    let payload: BytesN<32> = BytesN::random(&env);
+    let payload_hash = env.crypto().sha256(&payload.clone().into());
+
+    let credential = sign_ed25519(
+        env.clone(),
+        public_key,
+        &payload_hash.to_array(),
+        &signing_key,
+    );
+
+    env.try_invoke_contract_check_auth::<AccountContractError>(
+        &contract_address,
+        &BytesN::from_array(&env, &payload_hash.to_array()),
+        vec![&env, credential].into_val(&env),
+        &vec![&env],
+    )
+    .unwrap();
+}
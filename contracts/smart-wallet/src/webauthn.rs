@@ -1,10 +1,15 @@
-use soroban_sdk::{contracttype, crypto::Hash, panic_with_error, Bytes, BytesN, Env};
+use soroban_sdk::{contracttype, crypto::Hash, panic_with_error, Bytes, BytesN, Env, String, Vec};
 
-use crate::{base64_url, AccountContractError};
+use crate::{base64_url, AccountContractError, DataKey};
 
 /// The WebAuthn type for the get operation.
 pub(crate) const WEBAUTHN_TYPE_GET: &str = "webauthn.get";
 
+/// Offset of the RP ID hash in the authenticator data. It's the first thing in the structure.
+pub(crate) const AUTH_DATA_RP_ID_HASH_OFFSET: u32 = 0;
+/// Length in bytes of the RP ID hash.
+pub(crate) const AUTH_DATA_RP_ID_HASH_LEN: u32 = 32;
+
 /// Authenticator data flag offset. It appears after the RP ID hash in the authenticator data.
 pub(crate) const AUTH_DATA_FLAG_OFFSET: u32 = 32;
 /// Authenticator data flags for user presence
@@ -12,6 +17,10 @@ pub(crate) const AUTH_DATA_FLAG_UP: u8 = 0x01;
 /// Authenticator data flags for user verification
 pub(crate) const AUTH_DATA_FLAG_UV: u8 = 0x04;
 
+/// Offset of the 4-byte big-endian signature counter in the authenticator data.
+/// It appears right after the flags byte.
+pub(crate) const AUTH_DATA_SIGN_COUNT_OFFSET: u32 = 33;
+
 /// Length of the encoded challenge in the client data JSON.
 pub(crate) const ENCODED_CHALLENGE_LEN: u32 = 43;
 
@@ -40,12 +49,27 @@ struct ClientDataJson<'a> {
     pub r#type: &'a str,
     /// The challenge used in the WebAuthn operation.
     pub challenge: &'a str,
+    /// The origin of the relying party that requested the assertion.
+    pub origin: &'a str,
+}
+
+/// A signer's public key, tagged with the algorithm it's used with.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[contracttype]
+pub enum PublicKey {
+    /// A P-256 (secp256r1) public key, as produced by most WebAuthn authenticators.
+    Secp256r1(BytesN<65>),
+    /// An Ed25519 public key, as produced by authenticators using the EdDSA COSE algorithm
+    /// or by native Stellar signers.
+    Ed25519(BytesN<32>),
 }
 
 /// A WebAuthn credential.
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 #[contracttype]
 pub struct WebAuthnCredential {
+    /// The public key of the signer this credential was produced by.
+    pub public_key: PublicKey,
     /// The authenticator data is a base64url encoded string.
     pub authenticator_data: Bytes,
     /// The client data JSON is a base64url encoded string.
@@ -57,27 +81,40 @@ pub struct WebAuthnCredential {
 /// The `verify` function checks the validity of a WebAuthn signature.
 ///
 /// It performs the following checks:
-/// 1. Verifies the WebAuthn type.
-/// 2. Checks the authenticator data flags.
-/// 3. Validates the challenge.
-/// 4. Verifies the cryptographic signature.
+/// 1. Verifies the RP ID hash against the account's configured relying party.
+/// 2. Verifies the WebAuthn type.
+/// 3. Checks the authenticator data flags.
+/// 4. Validates the challenge.
+/// 5. Verifies the origin against the account's configured allowlist.
+/// 6. Verifies the cryptographic signature against `credential.public_key`, dispatching to
+///    the algorithm (secp256r1 or Ed25519) the key was registered with.
+/// 7. Verifies the signature counter has advanced since the last use, to detect cloned
+///    authenticators.
 ///
 /// # Arguments
 ///
 /// * `env` - The Soroban environment.
 /// * `signature_payload` - The payload used for signature verification.
-/// * `credential` - The WebAuthn credential containing the signature and other data.
-/// * `public_key` - The public key used for signature verification.
+/// * `credential` - The WebAuthn credential containing the signature, signer and other data.
 ///
 /// # Panics
 ///
 /// This function will panic if any of the checks fail.
-pub fn verify(
-    env: &Env,
-    signature_payload: &Hash<32>,
-    credential: &WebAuthnCredential,
-    public_key: &BytesN<65>,
-) {
+pub fn verify(env: &Env, signature_payload: &Hash<32>, credential: &WebAuthnCredential) {
+    // 1. Verify the RP ID hash
+    let rp_id_hash = credential.authenticator_data.slice(
+        AUTH_DATA_RP_ID_HASH_OFFSET..(AUTH_DATA_RP_ID_HASH_OFFSET + AUTH_DATA_RP_ID_HASH_LEN),
+    );
+    let expected_rp_id_hash = env
+        .storage()
+        .instance()
+        .get::<_, BytesN<32>>(&DataKey::RpIdHash)
+        .unwrap();
+
+    if rp_id_hash != Bytes::from_array(env, &expected_rp_id_hash.to_array()) {
+        panic_with_error!(env, AccountContractError::WebAuthnInvalidRpId);
+    }
+
     // Parse the client data JSON
     let client_data_json = credential
         .client_data_json
@@ -89,12 +126,12 @@ pub fn verify(
             panic_with_error!(env, AccountContractError::WebAuthnInvalidClientData);
         });
 
-    // 1. Verify the Webauthn type
+    // 2. Verify the Webauthn type
     if client_data.r#type != WEBAUTHN_TYPE_GET {
         panic_with_error!(env, AccountContractError::WebAuthnInvalidType);
     }
 
-    // 2. Verify the authenticator data flags
+    // 3. Verify the authenticator data flags
     let flags = credential
         .authenticator_data
         .get(AUTH_DATA_FLAG_OFFSET)
@@ -110,7 +147,7 @@ pub fn verify(
         panic_with_error!(env, AccountContractError::WebAuthnUserNotVerified);
     }
 
-    // 3. Verify the challenge
+    // 4. Verify the challenge
     let mut expected_challenge = [0_u8; ENCODED_CHALLENGE_LEN as usize];
     base64_url::encode(&mut expected_challenge, &signature_payload.to_array());
 
@@ -118,13 +155,65 @@ pub fn verify(
         panic_with_error!(env, AccountContractError::WebAuthnInvalidChallenge);
     }
 
-    // 4. Verify the cryptographic signature
+    // 5. Verify the origin against the allowlist
+    let allowed_origins = env
+        .storage()
+        .instance()
+        .get::<_, Vec<String>>(&DataKey::AllowedOrigins)
+        .unwrap();
+    let origin = String::from_str(env, client_data.origin);
+
+    if !allowed_origins.contains(&origin) {
+        panic_with_error!(env, AccountContractError::WebAuthnInvalidOrigin);
+    }
+
+    // 6. Verify the cryptographic signature
     let client_data_hash = env.crypto().sha256(&credential.client_data_json);
 
     let mut message = credential.authenticator_data.clone();
     message.extend_from_slice(&client_data_hash.to_array());
-    let message_hash = env.crypto().sha256(&message);
 
-    env.crypto()
-        .secp256r1_verify(public_key, &message_hash, &credential.signature);
+    match &credential.public_key {
+        PublicKey::Secp256r1(public_key) => {
+            let message_hash = env.crypto().sha256(&message);
+            env.crypto()
+                .secp256r1_verify(public_key, &message_hash, &credential.signature);
+        }
+        PublicKey::Ed25519(public_key) => {
+            // Ed25519 hashes its own input (SHA-512 internally); it must be handed the raw
+            // message, not a pre-hashed digest.
+            env.crypto()
+                .ed25519_verify(public_key, &message, &credential.signature);
+        }
+    }
+
+    // 7. Verify the signature counter, to detect cloned authenticators.
+    //
+    // A counter of 0 is accepted unconditionally, since some authenticators never
+    // increment it and always report 0.
+    let sign_count = {
+        let mut buf = [0_u8; 4];
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = credential
+                .authenticator_data
+                .get(AUTH_DATA_SIGN_COUNT_OFFSET + i as u32)
+                .unwrap();
+        }
+        u32::from_be_bytes(buf)
+    };
+
+    if sign_count != 0 {
+        let sign_count_key = DataKey::SignCount(credential.public_key.clone());
+        let last_sign_count = env
+            .storage()
+            .instance()
+            .get::<_, u32>(&sign_count_key)
+            .unwrap_or(0);
+
+        if sign_count <= last_sign_count {
+            panic_with_error!(env, AccountContractError::WebAuthnStaleCounter);
+        }
+
+        env.storage().instance().set(&sign_count_key, &sign_count);
+    }
 }